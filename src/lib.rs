@@ -1,12 +1,11 @@
 
 //! Macros for container comprehensions similar to Python's list comprehension.
 //!
-//! This crate adds vector, set, map, and generator comprehensions.  It is
+//! This crate adds vector, set, map, and iterator comprehensions.  It is
 //! meant to complement [maplit](https://docs.rs/maplit/) which provides
 //! macro literals for the same standard containers.
 //!
 //! ```rust
-//! # #![feature(match_default_bindings)]
 //! # #[macro_use] extern crate mapcomp;
 //! # fn main() {
 //! let v = vec![3, 2, 6, 9, 5];
@@ -19,7 +18,7 @@
 //!
 //! The macro names are the same as maplit's container literal macros but with
 //! a **c** at the end for **c**omprehension.  There is an additional macro
-//! `iterc!()` for creating lazily evaluated generator expressions.
+//! `iterc!()` for creating lazily evaluated iterator expressions.
 //!
 //! List comprehensions exist [in many languages](https://en.wikipedia.org/wiki/List_comprehension)
 //! and in many styles.  This crate uses the same syntax as Python's list
@@ -47,46 +46,15 @@
 //! ```
 
 
-#![feature(generators, generator_trait, arbitrary_self_types)]
-
-
-/// This is an implementation detail used by `iterc!()` and it should not be
-/// directly instantiated.
-#[doc(hidden)]
-pub struct GeneratorIterator<G: ::std::ops::Generator + ::std::marker::Unpin> {
-    generator: G,
-}
-
-impl<G: ::std::ops::Generator + ::std::marker::Unpin> GeneratorIterator<G> {
-    pub fn new(generator: G) -> GeneratorIterator<G> {
-        GeneratorIterator { generator }
-    }
-}
-
-impl<G: ::std::ops::Generator + ::std::marker::Unpin> Iterator for GeneratorIterator<G> {
-    type Item = G::Yield;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        use ::std::ops::GeneratorState;
-        match ::std::pin::Pin::new(&mut self.generator).resume() {
-            GeneratorState::Yielded(y) => Some(y),
-            _ => None,
-        }
-    }
-}
-
-
-
 /// Iterator Comprehension
 ///
 /// Returns an iterator over the contents of the comprehension.  It is
 /// analogous to [Python's generator comprehensions](https://www.python.org/dev/peps/pep-0289/).
 /// Syntactically, it is similar to the `vecc![]` macro but it returns a lazily
-/// evaluated iterator instead of a container.  It's use requires the experimental
-/// generators feature.
+/// evaluated iterator instead of a container.  It expands to a chain of
+/// `map`/`filter_map`/`flat_map` calls and runs on stable Rust.
 ///
 /// ```rust
-/// #![feature(generators, generator_trait)]
 /// #[macro_use]
 /// extern crate mapcomp;
 ///
@@ -107,7 +75,6 @@ impl<G: ::std::ops::Generator + ::std::marker::Unpin> Iterator for GeneratorIter
 /// comprehension can be created over an unbounded or infinite iterator.
 ///
 /// ```rust
-/// # #![feature(generators, generator_trait)]
 /// # #[macro_use] extern crate mapcomp;
 /// # fn main() {
 /// let mut odd_squares = iterc!(x * x; for x in 1..; if x % 2 == 1);
@@ -118,42 +85,162 @@ impl<G: ::std::ops::Generator + ::std::marker::Unpin> Iterator for GeneratorIter
 /// assert_eq!(Some(49), odd_squares.next());
 /// # }
 /// ```
+///
+/// An `if let PAT = EXPR` clause can be used anywhere `if COND` is accepted.
+/// The item is skipped when the pattern doesn't match, and any bindings it
+/// introduces are available to the rest of the comprehension.
+///
+/// ```rust
+/// # #[macro_use] extern crate mapcomp;
+/// # fn main() {
+/// let opts = [Some(2), None, Some(4), None, Some(6)];
+///
+/// let doubled: Vec<i32> = iterc!(x * 2; for &opt in &opts; if let Some(x) = opt).collect();
+///
+/// assert_eq!(doubled, vec![4, 8, 12]);
+/// # }
+/// ```
+///
+/// A `{ ... }` block clause placed right after a `for` clause runs statements
+/// at that nesting level, useful for debugging or counting without affecting
+/// the produced values.  The block is followed directly, with no semicolon, by
+/// the next `if` or `for` clause or by the end of the comprehension; a block
+/// after an `if` clause is not accepted.  Since `iterc!()` captures
+/// its clauses in `move` closures, side effects need a shared cell like
+/// `Cell` to remain observable after the comprehension runs.
+///
+/// ```rust
+/// # #[macro_use] extern crate mapcomp;
+/// # fn main() {
+/// use std::cell::Cell;
+///
+/// let seen = Cell::new(0);
+/// let seen_ref = &seen;
+///
+/// let evens: Vec<i32> = iterc!(x; for x in 0..6; { seen_ref.set(seen_ref.get() + 1); } if x % 2 == 0).collect();
+///
+/// assert_eq!(evens, vec![0, 2, 4]);
+/// assert_eq!(seen.get(), 6);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! iterc {
     (@__ $exp:expr; for $item:pat in $iter:expr; if $cond:expr) => (
-        for $item in $iter {
-            if $cond {
-                yield $exp;
-            }
-        }
+        ::std::iter::IntoIterator::into_iter($iter)
+            .filter_map(move |$item| if $cond { Some($exp) } else { None })
+    );
+
+    (@__ $exp:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr) => (
+        ::std::iter::IntoIterator::into_iter($iter)
+            .filter_map(move |$item| if let $letpat = $letexp { Some($exp) } else { None })
+    );
+
+    (@__ $exp:expr; for $item:pat in $iter:expr; $block:block if $cond:expr) => (
+        ::std::iter::IntoIterator::into_iter($iter)
+            .filter_map(move |$item| { $block; if $cond { Some($exp) } else { None } })
+    );
+
+    (@__ $exp:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr) => (
+        ::std::iter::IntoIterator::into_iter($iter)
+            .filter_map(move |$item| { $block; if let $letpat = $letexp { Some($exp) } else { None } })
+    );
+
+    (@__ $exp:expr; for $item:pat in $iter:expr; $block:block) => (
+        ::std::iter::IntoIterator::into_iter($iter)
+            .map(move |$item| { $block; $exp })
     );
 
     (@__ $exp:expr; for $item:pat in $iter:expr) => (
-        for $item in $iter {
-            yield $exp;
-        }
+        ::std::iter::IntoIterator::into_iter($iter)
+            .map(move |$item| $exp)
+    );
+
+    (@__ $exp:expr; for $item:pat in $iter:expr; $block:block if $cond:expr; $($tail:tt)+) => (
+        ::std::iter::IntoIterator::into_iter($iter)
+            .flat_map(move |$item| {
+                $block;
+                if $cond {
+                    Some(iterc!(@__ $exp; $($tail)+))
+                } else {
+                    None
+                }.into_iter().flatten()
+            })
+    );
+
+    (@__ $exp:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        ::std::iter::IntoIterator::into_iter($iter)
+            .flat_map(move |$item| {
+                $block;
+                if let $letpat = $letexp {
+                    Some(iterc!(@__ $exp; $($tail)+))
+                } else {
+                    None
+                }.into_iter().flatten()
+            })
+    );
+
+    (@__ $exp:expr; for $item:pat in $iter:expr; $block:block $($tail:tt)+) => (
+        ::std::iter::IntoIterator::into_iter($iter)
+            .flat_map(move |$item| { $block; iterc!(@__ $exp; $($tail)+) })
     );
 
     (@__ $exp:expr; for $item:pat in $iter:expr; if $cond:expr; $($tail:tt)+) => (
-        for $item in $iter {
-            if $cond {
-                iterc!(@__ $exp; $($tail)+)
-            }
-        }
+        ::std::iter::IntoIterator::into_iter($iter)
+            .flat_map(move |$item| {
+                if $cond {
+                    Some(iterc!(@__ $exp; $($tail)+))
+                } else {
+                    None
+                }.into_iter().flatten()
+            })
+    );
+
+    (@__ $exp:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        ::std::iter::IntoIterator::into_iter($iter)
+            .flat_map(move |$item| {
+                if let $letpat = $letexp {
+                    Some(iterc!(@__ $exp; $($tail)+))
+                } else {
+                    None
+                }.into_iter().flatten()
+            })
     );
 
     (@__ $exp:expr; for $item:pat in $iter:expr; $($tail:tt)+) => (
-        for $item in $iter {
-            iterc!(@__ $exp; $($tail)+)
-        }
+        ::std::iter::IntoIterator::into_iter($iter)
+            .flat_map(move |$item| iterc!(@__ $exp; $($tail)+))
     );
 
-    ($exp:expr; $($tail:tt)+) => ({
-        let mut generator = || {
-            iterc!(@__ $exp; $($tail)+)
-        };
-        ::mapcomp::GeneratorIterator::new(generator)
-    });
+    ($exp:expr; $($tail:tt)+) => (
+        iterc!(@__ $exp; $($tail)+)
+    );
+}
+
+
+
+/// Container-Generic Comprehension
+///
+/// Builds any container implementing `FromIterator` from the contents of the
+/// comprehension.  It takes the same clauses as `iterc!()` but is prefixed
+/// with the target type and `.collect()`s into it, so it can produce
+/// containers that don't have their own dedicated macro, such as
+/// `VecDeque`, `BinaryHeap`, `String`, or even `Result<Vec<_>, E>`.
+///
+/// ```rust
+/// # #[macro_use] extern crate mapcomp;
+/// # fn main() {
+/// let s = "a1 b2";
+///
+/// let alnum: String = collectc![String; c; for c in s.chars(); if c.is_alphanumeric()];
+///
+/// assert_eq!(alnum, "a1b2");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! collectc {
+    ($ty:ty; $exp:expr; $($tail:tt)+) => (
+        ::std::iter::Iterator::collect::<$ty>(iterc!(@__ $exp; $($tail)+))
+    );
 }
 
 
@@ -182,8 +269,80 @@ macro_rules! iterc {
 /// assert_eq!(even_squares, vec![16, 4]);
 /// # }
 /// ```
+///
+/// An `if let PAT = EXPR` clause can be used anywhere `if COND` is accepted,
+/// skipping items that don't match the pattern and binding the rest for use
+/// in later clauses and the output expression.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let opts = [Some(2), None, Some(4), None, Some(6)];
+///
+/// let doubled = vecc![x * 2; for &opt in &opts; if let Some(x) = opt];
+///
+/// assert_eq!(doubled, vec![4, 8, 12]);
+/// # }
+/// ```
+///
+/// Plain elements can be mixed with spliced-in sub-comprehensions by marking
+/// the spliced ones with a leading `*`, mirroring Python's `[0, 1, *range(8, 11), 15]`.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let v = vecc![0, 1, *(i; for i in 8..=10), 15];
+///
+/// assert_eq!(v, vec![0, 1, 8, 9, 10, 15]);
+/// # }
+/// ```
+///
+/// A leading `cap = n` clause initializes the `Vec` with `Vec::with_capacity(n)`
+/// instead of `Vec::new()`, avoiding reallocation when the bound of the source
+/// iterator is already known.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let v = vecc![cap = 5; x * x; for x in 0..5];
+///
+/// assert_eq!(v, vec![0, 1, 4, 9, 16]);
+/// # }
+/// ```
+///
+/// A `{ ... }` block clause placed right after a `for` clause runs statements
+/// at that nesting level, useful for debugging or counting without affecting
+/// the produced values.  The block is followed directly, with no semicolon, by
+/// the next `if` or `for` clause or by the end of the comprehension; a block
+/// after an `if` clause is not accepted.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let mut seen = 0;
+///
+/// let evens = vecc![x; for x in 0..6; { seen += 1; } if x % 2 == 0];
+///
+/// assert_eq!(evens, vec![0, 2, 4]);
+/// assert_eq!(seen, 6);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! vecc {
+    (@__list $acc:ident; * ( $sexp:expr; $($stail:tt)+ )) => (
+        vecc![@__ $acc, $sexp; $($stail)+];
+    );
+
+    (@__list $acc:ident; * ( $sexp:expr; $($stail:tt)+ ), $($rest:tt)+) => (
+        vecc![@__ $acc, $sexp; $($stail)+];
+        vecc![@__list $acc; $($rest)+];
+    );
+
+    (@__list $acc:ident; $elem:expr) => (
+        $acc.push($elem);
+    );
+
+    (@__list $acc:ident; $elem:expr, $($rest:tt)+) => (
+        $acc.push($elem);
+        vecc![@__list $acc; $($rest)+];
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if $cond:expr) => (
         for $item in $iter {
             if $cond {
@@ -192,12 +351,70 @@ macro_rules! vecc {
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                $acc.push($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if $cond:expr) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                $acc.push($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                $acc.push($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block) => (
+        for $item in $iter {
+            $block
+            $acc.push($exp);
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr) => (
         for $item in $iter {
             $acc.push($exp);
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if $cond:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                vecc![@__ $acc, $exp; $($tail)+];
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                vecc![@__ $acc, $exp; $($tail)+];
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            vecc![@__ $acc, $exp; $($tail)+];
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if $cond:expr; $($tail:tt)+) => (
         for $item in $iter {
             if $cond {
@@ -206,17 +423,50 @@ macro_rules! vecc {
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                vecc![@__ $acc, $exp; $($tail)+];
+            }
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $($tail:tt)+) => (
         for $item in $iter {
             vecc![@__ $acc, $exp; $($tail)+];
         }
     );
 
+    (cap = $cap:expr; $exp:expr; $($tail:tt)+) => ({
+        let mut ret = ::std::vec::Vec::with_capacity($cap);
+        vecc![@__ ret, $exp; $($tail)+];
+        ret
+    });
+
+    (* ( $sexp:expr; $($stail:tt)+ )) => ({
+        let mut ret = ::std::vec::Vec::new();
+        vecc![@__ ret, $sexp; $($stail)+];
+        ret
+    });
+
+    (* ( $sexp:expr; $($stail:tt)+ ), $($rest:tt)+) => ({
+        let mut ret = ::std::vec::Vec::new();
+        vecc![@__ ret, $sexp; $($stail)+];
+        vecc![@__list ret; $($rest)+];
+        ret
+    });
+
     ($exp:expr; $($tail:tt)+) => ({
         let mut ret = ::std::vec::Vec::new();
         vecc![@__ ret, $exp; $($tail)+];
         ret
     });
+
+    ($($elem:tt)+) => ({
+        let mut ret = ::std::vec::Vec::new();
+        vecc![@__list ret; $($elem)+];
+        ret
+    });
 }
 
 
@@ -248,8 +498,71 @@ macro_rules! vecc {
 /// }
 /// # }
 /// ```
+///
+/// Plain elements can be mixed with spliced-in sub-comprehensions by marking
+/// the spliced ones with a leading `*`.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let members = hashsetc!{0, *(i; for i in 8..=10), 15};
+///
+/// for n in &[0, 8, 9, 10, 15] {
+///     assert!(members.contains(n));
+/// }
+/// # }
+/// ```
+///
+/// A leading `cap = n` clause initializes the `HashSet` with
+/// `HashSet::with_capacity(n)` instead of `HashSet::new()`.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let members = hashsetc!{cap = 3; x * x; for x in 0..3};
+///
+/// for n in &[0, 1, 4] {
+///     assert!(members.contains(n));
+/// }
+/// # }
+/// ```
+///
+/// A `{ ... }` block clause placed right after a `for` clause runs statements
+/// at that nesting level, useful for debugging or counting without affecting
+/// the produced values.  The block is followed directly, with no semicolon, by
+/// the next `if` or `for` clause or by the end of the comprehension; a block
+/// after an `if` clause is not accepted.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let mut seen = 0;
+///
+/// let evens = hashsetc!{x; for x in 0..6; { seen += 1; } if x % 2 == 0};
+///
+/// for n in &[0, 2, 4] {
+///     assert!(evens.contains(n));
+/// }
+/// assert_eq!(seen, 6);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! hashsetc {
+    (@__list $acc:ident; * ( $sexp:expr; $($stail:tt)+ )) => (
+        hashsetc!{@__ $acc, $sexp; $($stail)+};
+    );
+
+    (@__list $acc:ident; * ( $sexp:expr; $($stail:tt)+ ), $($rest:tt)+) => (
+        hashsetc!{@__ $acc, $sexp; $($stail)+};
+        hashsetc!{@__list $acc; $($rest)+};
+    );
+
+    (@__list $acc:ident; $elem:expr) => (
+        $acc.insert($elem);
+    );
+
+    (@__list $acc:ident; $elem:expr, $($rest:tt)+) => (
+        $acc.insert($elem);
+        hashsetc!{@__list $acc; $($rest)+};
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if $cond:expr) => (
         for $item in $iter {
             if $cond {
@@ -258,12 +571,70 @@ macro_rules! hashsetc {
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                $acc.insert($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if $cond:expr) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                $acc.insert($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                $acc.insert($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block) => (
+        for $item in $iter {
+            $block
+            $acc.insert($exp);
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr) => (
         for $item in $iter {
             $acc.insert($exp);
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if $cond:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                hashsetc!{@__ $acc, $exp; $($tail)+};
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                hashsetc!{@__ $acc, $exp; $($tail)+};
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            hashsetc!{@__ $acc, $exp; $($tail)+};
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if $cond:expr; $($tail:tt)+) => (
         for $item in $iter {
             if $cond {
@@ -272,17 +643,50 @@ macro_rules! hashsetc {
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                hashsetc!{@__ $acc, $exp; $($tail)+};
+            }
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $($tail:tt)+) => (
         for $item in $iter {
             hashsetc!{@__ $acc, $exp; $($tail)+};
         }
     );
 
+    (cap = $cap:expr; $exp:expr; $($tail:tt)+) => ({
+        let mut ret = ::std::collections::HashSet::with_capacity($cap);
+        hashsetc!{@__ ret, $exp; $($tail)+};
+        ret
+    });
+
+    (* ( $sexp:expr; $($stail:tt)+ )) => ({
+        let mut ret = ::std::collections::HashSet::new();
+        hashsetc!{@__ ret, $sexp; $($stail)+};
+        ret
+    });
+
+    (* ( $sexp:expr; $($stail:tt)+ ), $($rest:tt)+) => ({
+        let mut ret = ::std::collections::HashSet::new();
+        hashsetc!{@__ ret, $sexp; $($stail)+};
+        hashsetc!{@__list ret; $($rest)+};
+        ret
+    });
+
     ($exp:expr; $($tail:tt)+) => ({
         let mut ret = ::std::collections::HashSet::new();
         hashsetc!{@__ ret, $exp; $($tail)+};
         ret
     });
+
+    ($($elem:tt)+) => ({
+        let mut ret = ::std::collections::HashSet::new();
+        hashsetc!{@__list ret; $($elem)+};
+        ret
+    });
 }
 
 
@@ -315,6 +719,42 @@ macro_rules! hashsetc {
 /// }
 /// # }
 /// ```
+///
+/// A leading `cap = n` clause initializes the `HashMap` with
+/// `HashMap::with_capacity(n)` instead of `HashMap::new()`.
+///
+/// ```rust
+/// # #[macro_use] extern crate mapcomp;
+/// # fn main() {
+/// let numbers = [6, 4, 18];
+///
+/// let halves = hashmapc!{cap = 3; x.to_string() => x / 2; for x in numbers.iter()};
+///
+/// for &(k, v) in &[("6", 3), ("4", 2), ("18", 9)] {
+///     assert_eq!(halves[k], v);
+/// }
+/// # }
+/// ```
+///
+/// A `{ ... }` block clause placed right after a `for` clause runs statements
+/// at that nesting level, useful for debugging or counting without affecting
+/// the produced values.  The block is followed directly, with no semicolon, by
+/// the next `if` or `for` clause or by the end of the comprehension; a block
+/// after an `if` clause is not accepted.
+///
+/// ```rust
+/// # #[macro_use] extern crate mapcomp;
+/// # fn main() {
+/// let mut seen = 0;
+///
+/// let halves = hashmapc!{x => x / 2; for x in 0..6; { seen += 1; } if x % 2 == 0};
+///
+/// for &(k, v) in &[(0, 0), (2, 1), (4, 2)] {
+///     assert_eq!(halves[&k], v);
+/// }
+/// assert_eq!(seen, 6);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! hashmapc {
     (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; if $cond:expr) => (
@@ -325,12 +765,70 @@ macro_rules! hashmapc {
         }
     );
 
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                $acc.insert($key, $val);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block if $cond:expr) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                $acc.insert($key, $val);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                $acc.insert($key, $val);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block) => (
+        for $item in $iter {
+            $block
+            $acc.insert($key, $val);
+        }
+    );
+
     (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr) => (
         for $item in $iter {
             $acc.insert($key, $val);
         }
     );
 
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block if $cond:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                hashmapc!{@__ $acc, $key => $val; $($tail)+};
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                hashmapc!{@__ $acc, $key => $val; $($tail)+};
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            hashmapc!{@__ $acc, $key => $val; $($tail)+};
+        }
+    );
+
     (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; if $cond:expr; $($tail:tt)+) => (
         for $item in $iter {
             if $cond {
@@ -339,12 +837,26 @@ macro_rules! hashmapc {
         }
     );
 
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                hashmapc!{@__ $acc, $key => $val; $($tail)+};
+            }
+        }
+    );
+
     (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $($tail:tt)+) => (
         for $item in $iter {
             hashmapc!{@__ $acc, $key => $val; $($tail)+};
         }
     );
 
+    (cap = $cap:expr; $key:expr => $val:expr; $($tail:tt)+) => ({
+        let mut ret = ::std::collections::HashMap::with_capacity($cap);
+        hashmapc!{@__ ret, $key => $val; $($tail)+};
+        ret
+    });
+
     ($key:expr => $val:expr; $($tail:tt)+) => ({
         let mut ret = ::std::collections::HashMap::new();
         hashmapc!{@__ ret, $key => $val; $($tail)+};
@@ -360,7 +872,6 @@ macro_rules! hashmapc {
 /// `hashsetc!{}`.
 ///
 /// ```rust
-/// # #![feature(match_default_bindings)]
 /// # #[macro_use] extern crate mapcomp;
 /// # fn main() {
 /// let pairs = btreesetc!{(i, j); for i in 4..7; for j in 10..12};
@@ -372,8 +883,56 @@ macro_rules! hashmapc {
 /// }
 /// # }
 /// ```
+///
+/// Plain elements can be mixed with spliced-in sub-comprehensions by marking
+/// the spliced ones with a leading `*`.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let set = btreesetc!{0, *(i; for i in 8..=10), 15};
+///
+/// assert_eq!(set, btreesetc!{0, 8, 9, 10, 15});
+/// # }
+/// ```
+///
+/// Unlike `hashsetc!{}`, there is no leading `cap = n` clause.
+///
+/// A `{ ... }` block clause placed right after a `for` clause runs statements
+/// at that nesting level, useful for debugging or counting without affecting
+/// the produced values.  The block is followed directly, with no semicolon, by
+/// the next `if` or `for` clause or by the end of the comprehension; a block
+/// after an `if` clause is not accepted.
+///
+/// ```
+/// # #[macro_use] extern crate mapcomp; fn main() {
+/// let mut seen = 0;
+///
+/// let evens = btreesetc!{x; for x in 0..6; { seen += 1; } if x % 2 == 0};
+///
+/// assert_eq!(evens, btreesetc!{0, 2, 4});
+/// assert_eq!(seen, 6);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! btreesetc {
+    (@__list $acc:ident; * ( $sexp:expr; $($stail:tt)+ )) => (
+        btreesetc!{@__ $acc, $sexp; $($stail)+};
+    );
+
+    (@__list $acc:ident; * ( $sexp:expr; $($stail:tt)+ ), $($rest:tt)+) => (
+        btreesetc!{@__ $acc, $sexp; $($stail)+};
+        btreesetc!{@__list $acc; $($rest)+};
+    );
+
+    (@__list $acc:ident; $elem:expr) => (
+        $acc.insert($elem);
+    );
+
+    (@__list $acc:ident; $elem:expr, $($rest:tt)+) => (
+        $acc.insert($elem);
+        btreesetc!{@__list $acc; $($rest)+};
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if $cond:expr) => (
         for $item in $iter {
             if $cond {
@@ -382,12 +941,70 @@ macro_rules! btreesetc {
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                $acc.insert($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if $cond:expr) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                $acc.insert($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                $acc.insert($exp);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block) => (
+        for $item in $iter {
+            $block
+            $acc.insert($exp);
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr) => (
         for $item in $iter {
             $acc.insert($exp);
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if $cond:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                btreesetc!{@__ $acc, $exp; $($tail)+};
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                btreesetc!{@__ $acc, $exp; $($tail)+};
+            }
+        }
+    );
+
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $block:block $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            btreesetc!{@__ $acc, $exp; $($tail)+};
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if $cond:expr; $($tail:tt)+) => (
         for $item in $iter {
             if $cond {
@@ -396,17 +1013,44 @@ macro_rules! btreesetc {
         }
     );
 
+    (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                btreesetc!{@__ $acc, $exp; $($tail)+};
+            }
+        }
+    );
+
     (@__ $acc:ident, $exp:expr; for $item:pat in $iter:expr; $($tail:tt)+) => (
         for $item in $iter {
             btreesetc!{@__ $acc, $exp; $($tail)+};
         }
     );
 
+    (* ( $sexp:expr; $($stail:tt)+ )) => ({
+        let mut ret = ::std::collections::BTreeSet::new();
+        btreesetc!{@__ ret, $sexp; $($stail)+};
+        ret
+    });
+
+    (* ( $sexp:expr; $($stail:tt)+ ), $($rest:tt)+) => ({
+        let mut ret = ::std::collections::BTreeSet::new();
+        btreesetc!{@__ ret, $sexp; $($stail)+};
+        btreesetc!{@__list ret; $($rest)+};
+        ret
+    });
+
     ($exp:expr; $($tail:tt)+) => ({
         let mut ret = ::std::collections::BTreeSet::new();
         btreesetc!{@__ ret, $exp; $($tail)+};
         ret
     });
+
+    ($($elem:tt)+) => ({
+        let mut ret = ::std::collections::BTreeSet::new();
+        btreesetc!{@__list ret; $($elem)+};
+        ret
+    });
 }
 
 
@@ -428,6 +1072,28 @@ macro_rules! btreesetc {
 /// }
 /// # }
 /// ```
+///
+/// A `{ ... }` block clause placed right after a `for` clause runs statements
+/// at that nesting level, useful for debugging or counting without affecting
+/// the produced values.  The block is followed directly, with no semicolon, by
+/// the next `if` or `for` clause or by the end of the comprehension; a block
+/// after an `if` clause is not accepted.
+///
+/// ```rust
+/// # #[macro_use] extern crate mapcomp;
+/// # fn main() {
+/// let mut seen = 0;
+///
+/// let halves = btreemapc!{x => x / 2; for x in 0..6; { seen += 1; } if x % 2 == 0};
+///
+/// for &(k, v) in &[(0, 0), (2, 1), (4, 2)] {
+///     assert_eq!(halves[&k], v);
+/// }
+/// assert_eq!(seen, 6);
+/// # }
+/// ```
+///
+/// Unlike `hashmapc!{}`, there is no leading `cap = n` clause.
 #[macro_export]
 macro_rules! btreemapc {
     (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; if $cond:expr) => (
@@ -438,12 +1104,70 @@ macro_rules! btreemapc {
         }
     );
 
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                $acc.insert($key, $val);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block if $cond:expr) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                $acc.insert($key, $val);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                $acc.insert($key, $val);
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block) => (
+        for $item in $iter {
+            $block
+            $acc.insert($key, $val);
+        }
+    );
+
     (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr) => (
         for $item in $iter {
             $acc.insert($key, $val);
         }
     );
 
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block if $cond:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if $cond {
+                btreemapc!{@__ $acc, $key => $val; $($tail)+};
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            if let $letpat = $letexp {
+                btreemapc!{@__ $acc, $key => $val; $($tail)+};
+            }
+        }
+    );
+
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $block:block $($tail:tt)+) => (
+        for $item in $iter {
+            $block
+            btreemapc!{@__ $acc, $key => $val; $($tail)+};
+        }
+    );
+
     (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; if $cond:expr; $($tail:tt)+) => (
         for $item in $iter {
             if $cond {
@@ -452,6 +1176,14 @@ macro_rules! btreemapc {
         }
     );
 
+    (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; if let $letpat:pat = $letexp:expr; $($tail:tt)+) => (
+        for $item in $iter {
+            if let $letpat = $letexp {
+                btreemapc!{@__ $acc, $key => $val; $($tail)+};
+            }
+        }
+    );
+
     (@__ $acc:ident, $key:expr => $val:expr; for $item:pat in $iter:expr; $($tail:tt)+) => (
         for $item in $iter {
             btreemapc!{@__ $acc, $key => $val; $($tail)+};